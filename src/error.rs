@@ -0,0 +1,38 @@
+use std::fmt;
+
+/// Crate-wide error type returned by `Provider` implementations, so that e.g. a
+/// legitimately empty day (no datapoints) returns zero instead of panicking, and callers
+/// can distinguish "we couldn't authenticate" from "the API is down".
+#[derive(Debug)]
+pub enum HermesError {
+    /// The OAuth consent/token flow failed.
+    AuthFailure(String),
+    /// No client secret path was provided, or no file exists at the configured path.
+    MissingCredentials(String),
+    /// The underlying Fitness API call itself failed (network error, non-2xx response).
+    ApiError(anyhow::Error),
+    /// The API responded successfully but Hermes couldn't make sense of the payload.
+    MalformedResponse(String),
+}
+
+impl fmt::Display for HermesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HermesError::AuthFailure(msg) => write!(f, "authentication failed: {}", msg),
+            HermesError::MissingCredentials(msg) => write!(f, "missing credentials: {}", msg),
+            HermesError::ApiError(err) => write!(f, "Fitness API error: {}", err),
+            HermesError::MalformedResponse(msg) => write!(f, "malformed API response: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for HermesError {}
+
+impl From<anyhow::Error> for HermesError {
+    fn from(err: anyhow::Error) -> Self {
+        if err.is::<async_google_apis_common::yup_oauth2::Error>() {
+            return HermesError::AuthFailure(err.to_string());
+        }
+        HermesError::ApiError(err)
+    }
+}
@@ -1,30 +1,44 @@
+mod error;
 mod providers;
 use env_logger;
+use error::HermesError;
 use providers::{google::GoogleFitProvider, Provider};
 use std::path::Path;
 
-fn validate_env() {
-    let google_client_secret_path = std::env::var("GOOGLE_CLIENT_SECRET")
-        .expect("Missing env variable: `GOOGLE_CLIENT_SECRET`");
+fn validate_env() -> Result<String, HermesError> {
+    let google_client_secret_path = std::env::var("GOOGLE_CLIENT_SECRET").map_err(|_| {
+        HermesError::MissingCredentials("GOOGLE_CLIENT_SECRET is not set".to_string())
+    })?;
     if !Path::new(&google_client_secret_path).exists() {
-        panic!(
-            "Invalid Google client secret path: {}",
+        return Err(HermesError::MissingCredentials(format!(
+            "no client secret file at {}",
             google_client_secret_path
-        )
+        )));
     }
+    Ok(google_client_secret_path)
 }
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    validate_env();
-    let secret_path = std::env::var("GOOGLE_CLIENT_SECRET").unwrap();
-    let gfit = GoogleFitProvider::new(Some(secret_path))
-        .await
-        .expect("Failed to initialize GoogleFitProvider");
-    let steps = gfit.daily_steps().await;
-    match steps {
+    let secret_path = match validate_env() {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let gfit = match GoogleFitProvider::new(Some(secret_path)).await {
+        Ok(provider) => provider,
+        Err(e) => {
+            eprintln!("Failed to initialize GoogleFitProvider: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match gfit.daily_steps().await {
         Ok(n) => println!("You have taken {:?} steps today!", n),
-        Err(e) => panic!("Failed to retrieve steps :( {}", e),
+        Err(e) => eprintln!("Failed to retrieve steps: {}", e),
     }
 }
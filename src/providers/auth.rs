@@ -0,0 +1,124 @@
+//! Shared OAuth credential management for `Provider` implementations.
+//!
+//! `AuthManager` owns a single `InstalledFlowAuthenticator` and caches the bearer token it
+//! hands out, so a provider only pays for the refresh/re-auth flow once the cached token has
+//! actually expired rather than on every request.
+use async_google_apis_common as common;
+use common::yup_oauth2::{InstalledFlowAuthenticator, InstalledFlowReturnMethod};
+use hyper::client::HttpConnector;
+use hyper_rustls::HttpsConnector;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+/// How the OAuth consent flow should be completed.
+#[derive(Debug, Clone, Copy)]
+pub enum AuthRedirectMethod {
+    /// Runs a local HTTP listener and redirects the user's browser back to it. Suitable
+    /// for a desktop session with a browser available.
+    HttpRedirect,
+    /// Prints a URL and asks the user to paste back the resulting code. Suitable for a
+    /// headless server with no local browser to redirect to.
+    Interactive,
+}
+
+impl From<AuthRedirectMethod> for InstalledFlowReturnMethod {
+    fn from(method: AuthRedirectMethod) -> Self {
+        match method {
+            AuthRedirectMethod::HttpRedirect => InstalledFlowReturnMethod::HTTPRedirect,
+            AuthRedirectMethod::Interactive => InstalledFlowReturnMethod::Interactive,
+        }
+    }
+}
+
+struct CachedToken {
+    bearer: String,
+    expires_at_unix: u64,
+}
+
+/// Caches a bearer token per distinct scope set, refreshing a given set only once its
+/// cached token has expired. Shared (via `Arc`) across however many service wrappers a
+/// `Provider` needs, so they don't each run their own auth flow or persist their own token
+/// file — each service still gets back a token scoped to what it actually asked for.
+pub struct AuthManager {
+    https_client: common::TlsClient,
+    authenticator: common::yup_oauth2::authenticator::Authenticator<HttpsConnector<HttpConnector>>,
+    cached: Mutex<HashMap<Vec<String>, CachedToken>>,
+}
+
+impl AuthManager {
+    /// Builds an `AuthManager` from a client secret file, persisting tokens to
+    /// `token_cache_path` and completing the consent flow via `redirect_method`.
+    pub async fn new(
+        client_secret_path: String,
+        token_cache_path: String,
+        redirect_method: AuthRedirectMethod,
+    ) -> anyhow::Result<AuthManager> {
+        let https_client = Self::generate_https_client();
+        let secrets = common::yup_oauth2::read_application_secret(client_secret_path).await?;
+        let authenticator =
+            InstalledFlowAuthenticator::builder(secrets, redirect_method.into())
+                .persist_tokens_to_disk(token_cache_path)
+                .hyper_client(https_client.clone())
+                .build()
+                .await?;
+
+        Ok(AuthManager {
+            https_client,
+            authenticator,
+            cached: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The HTTPS client the authenticator was built with, reused by service wrappers so
+    /// they don't each stand up their own connection pool.
+    pub fn https_client(&self) -> common::TlsClient {
+        self.https_client.clone()
+    }
+
+    /// Returns a bearer token valid for `scopes`, refreshing it only if the token cached
+    /// for that exact scope set (if any) has expired against the current UNIX time. A
+    /// cached token minted for a different scope set is never handed back here, since a
+    /// read-scoped token would be rejected by an endpoint that needs write access.
+    pub async fn bearer_token(&self, scopes: &[&str]) -> anyhow::Result<String> {
+        let cache_key: Vec<String> = scopes.iter().map(|s| s.to_string()).collect();
+
+        if let Some(token) = self.cached.lock().await.get(&cache_key) {
+            if token.expires_at_unix > Self::now_unix() {
+                return Ok(token.bearer.clone());
+            }
+        }
+
+        let token = self.authenticator.token(scopes).await?;
+        let bearer = token
+            .token()
+            .ok_or_else(|| anyhow::anyhow!("authenticator returned an empty access token"))?
+            .to_string();
+        let expires_at_unix = token
+            .expiration_time()
+            .map(|expiry| expiry.timestamp() as u64)
+            .unwrap_or_else(|| Self::now_unix() + 3600);
+
+        self.cached.lock().await.insert(
+            cache_key,
+            CachedToken {
+                bearer: bearer.clone(),
+                expires_at_unix,
+            },
+        );
+        Ok(bearer)
+    }
+
+    fn now_unix() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn generate_https_client() -> common::TlsClient {
+        let conn = hyper_rustls::HttpsConnector::with_native_roots();
+        hyper::Client::builder().build(conn)
+    }
+}
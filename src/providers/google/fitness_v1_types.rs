@@ -0,0 +1,278 @@
+//! Request/response types and thin service wrappers for the subset of the
+//! Google Fitness REST API (v1) that `GoogleFitProvider` needs. These mirror
+//! the shapes documented at
+//! https://developers.google.com/fit/rest/v1/reference, trimmed to the
+//! fields we actually read or write.
+use async_google_apis_common as common;
+use common::serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::providers::auth::AuthManager;
+
+/// OAuth scopes used to authorize access to the Fitness API.
+/// See https://developers.google.com/fit/rest/v1/authorization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitnessScopes {
+    FitnessActivityRead,
+    FitnessActivityWrite,
+    FitnessBodyRead,
+    FitnessLocationRead,
+}
+
+impl FitnessScopes {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FitnessScopes::FitnessActivityRead => {
+                "https://www.googleapis.com/auth/fitness.activity.read"
+            }
+            FitnessScopes::FitnessActivityWrite => {
+                "https://www.googleapis.com/auth/fitness.activity.write"
+            }
+            FitnessScopes::FitnessBodyRead => "https://www.googleapis.com/auth/fitness.body.read",
+            FitnessScopes::FitnessLocationRead => {
+                "https://www.googleapis.com/auth/fitness.location.read"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregateBy {
+    #[serde(rename = "dataSourceId", skip_serializing_if = "Option::is_none")]
+    pub data_source_id: Option<String>,
+    #[serde(rename = "dataTypeName", skip_serializing_if = "Option::is_none")]
+    pub data_type_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BucketByTime {
+    #[serde(rename = "durationMillis", skip_serializing_if = "Option::is_none")]
+    pub duration_millis: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregateRequest {
+    #[serde(rename = "aggregateBy", skip_serializing_if = "Option::is_none")]
+    pub aggregate_by: Option<Vec<AggregateBy>>,
+    #[serde(rename = "bucketByTime", skip_serializing_if = "Option::is_none")]
+    pub bucket_by_time: Option<BucketByTime>,
+    #[serde(rename = "startTimeMillis", skip_serializing_if = "Option::is_none")]
+    pub start_time_millis: Option<String>,
+    #[serde(rename = "endTimeMillis", skip_serializing_if = "Option::is_none")]
+    pub end_time_millis: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Value {
+    #[serde(rename = "intVal", skip_serializing_if = "Option::is_none")]
+    pub int_val: Option<i32>,
+    #[serde(rename = "fpVal", skip_serializing_if = "Option::is_none")]
+    pub fp_val: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataPoint {
+    #[serde(rename = "startTimeNanos", skip_serializing_if = "Option::is_none")]
+    pub start_time_nanos: Option<String>,
+    #[serde(rename = "endTimeNanos", skip_serializing_if = "Option::is_none")]
+    pub end_time_nanos: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Dataset {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub point: Option<Vec<DataPoint>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregateBucket {
+    #[serde(rename = "startTimeMillis", skip_serializing_if = "Option::is_none")]
+    pub start_time_millis: Option<String>,
+    #[serde(rename = "endTimeMillis", skip_serializing_if = "Option::is_none")]
+    pub end_time_millis: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<Vec<Dataset>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregateResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<Vec<AggregateBucket>>,
+}
+
+/// Path parameters for `users.dataset.aggregate`.
+#[derive(Debug, Clone, Default)]
+pub struct UsersDatasetAggregateParams {
+    pub user_id: String,
+    pub fitness_params: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Application {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataTypeField {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataType {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<Vec<DataTypeField>>,
+}
+
+/// A `DataSource` describes the application and stream writing to (or read from) a user's
+/// Fitness dataset. See https://developers.google.com/fit/rest/v1/reference/users/dataSources.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataSource {
+    #[serde(rename = "dataStreamId", skip_serializing_if = "Option::is_none")]
+    pub data_stream_id: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application: Option<Application>,
+    #[serde(rename = "dataType", skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<DataType>,
+}
+
+/// Response body for `users.dataSources.list`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListDataSourcesResponse {
+    #[serde(rename = "dataSource", skip_serializing_if = "Option::is_none")]
+    pub data_source: Option<Vec<DataSource>>,
+}
+
+/// Request/response body for `users.dataSources.datasets.patch`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatasetPatchRequest {
+    #[serde(rename = "dataSourceId", skip_serializing_if = "Option::is_none")]
+    pub data_source_id: Option<String>,
+    #[serde(rename = "minStartTimeNs", skip_serializing_if = "Option::is_none")]
+    pub min_start_time_ns: Option<String>,
+    #[serde(rename = "maxEndTimeNs", skip_serializing_if = "Option::is_none")]
+    pub max_end_time_ns: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub point: Option<Vec<DataPoint>>,
+}
+
+/// Path parameters for `users.dataSources.create` and `users.dataSources.datasets.patch`.
+#[derive(Debug, Clone, Default)]
+pub struct UsersDataSourceParams {
+    pub user_id: String,
+}
+
+/// Thin wrapper around the `users.dataset` resource of the Fitness API.
+pub struct UsersDatasetService {
+    client: common::TlsClient,
+    auth_manager: Arc<AuthManager>,
+}
+
+impl UsersDatasetService {
+    pub fn new(client: common::TlsClient, auth_manager: Arc<AuthManager>) -> Self {
+        UsersDatasetService {
+            client,
+            auth_manager,
+        }
+    }
+
+    /// POSTs an `AggregateRequest` to `users/{userId}/dataset:aggregate` and returns the
+    /// parsed `AggregateResponse`. `scopes` is the minimal set of scopes this particular
+    /// call needs; callers requesting different metrics should pass different scopes so a
+    /// token is never minted with more access than the call actually uses.
+    pub async fn aggregate(
+        &self,
+        params: &UsersDatasetAggregateParams,
+        request: &AggregateRequest,
+        scopes: &[FitnessScopes],
+    ) -> anyhow::Result<AggregateResponse> {
+        let url = format!(
+            "https://www.googleapis.com/fitness/v1/users/{}/dataset:aggregate",
+            params.user_id
+        );
+        let scope_strs: Vec<&'static str> = scopes.iter().map(|s| s.as_str()).collect();
+        let token = self.auth_manager.bearer_token(&scope_strs).await?;
+        common::execute_request(&self.client, &url, &token, request).await
+    }
+}
+
+/// Thin wrapper around the `users.dataSources` resource of the Fitness API.
+pub struct UsersDataSourceService {
+    client: common::TlsClient,
+    auth_manager: Arc<AuthManager>,
+    scopes: Vec<FitnessScopes>,
+}
+
+impl UsersDataSourceService {
+    pub fn new(client: common::TlsClient, auth_manager: Arc<AuthManager>) -> Self {
+        UsersDataSourceService {
+            client,
+            auth_manager,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn set_scopes(&mut self, scopes: Vec<FitnessScopes>) {
+        self.scopes = scopes;
+    }
+
+    /// GETs `users/{userId}/dataSources` and returns every data source registered for the
+    /// user, so callers can check whether one they'd otherwise create already exists.
+    pub async fn list(
+        &self,
+        params: &UsersDataSourceParams,
+    ) -> anyhow::Result<ListDataSourcesResponse> {
+        let url = format!(
+            "https://www.googleapis.com/fitness/v1/users/{}/dataSources",
+            params.user_id
+        );
+        let token = self.auth_manager.bearer_token(&self.scope_strs()).await?;
+        common::execute_request(&self.client, &url, &token, &()).await
+    }
+
+    /// POSTs a `DataSource` to `users/{userId}/dataSources`, registering it.
+    pub async fn create(
+        &self,
+        params: &UsersDataSourceParams,
+        data_source: &DataSource,
+    ) -> anyhow::Result<DataSource> {
+        let url = format!(
+            "https://www.googleapis.com/fitness/v1/users/{}/dataSources",
+            params.user_id
+        );
+        let token = self.auth_manager.bearer_token(&self.scope_strs()).await?;
+        common::execute_request(&self.client, &url, &token, data_source).await
+    }
+
+    /// PATCHes a dataset on `data_source_id`, identified by `dataset_id` (of the form
+    /// `{startNanos}-{endNanos}`), at `users/{userId}/dataSources/{dataSourceId}/datasets/{datasetId}`.
+    pub async fn datasets_patch(
+        &self,
+        params: &UsersDataSourceParams,
+        data_source_id: &str,
+        dataset_id: &str,
+        dataset: &DatasetPatchRequest,
+    ) -> anyhow::Result<DatasetPatchRequest> {
+        let url = format!(
+            "https://www.googleapis.com/fitness/v1/users/{}/dataSources/{}/datasets/{}",
+            params.user_id, data_source_id, dataset_id
+        );
+        let token = self.auth_manager.bearer_token(&self.scope_strs()).await?;
+        common::execute_request(&self.client, &url, &token, dataset).await
+    }
+
+    fn scope_strs(&self) -> Vec<&'static str> {
+        self.scopes.iter().map(|s| s.as_str()).collect()
+    }
+}
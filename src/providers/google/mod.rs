@@ -1,28 +1,68 @@
 pub mod fitness_v1_types;
-use async_google_apis_common as common;
 use async_trait::async_trait;
-use chrono;
-use common::yup_oauth2::{InstalledFlowAuthenticator, InstalledFlowReturnMethod};
-use hyper::client::HttpConnector;
-use hyper_rustls::HttpsConnector;
+use chrono::{DateTime, TimeZone, Utc};
 use std::sync::Arc;
 
+use crate::error::HermesError;
+use crate::providers::auth::{AuthManager, AuthRedirectMethod};
 use crate::providers::google::fitness_v1_types::{
-    AggregateBy, AggregateResponse, BucketByTime, UsersDatasetAggregateParams,
+    AggregateBy, AggregateResponse, Application, BucketByTime, DataSource, DataType,
+    DataTypeField, DatasetPatchRequest, UsersDataSourceParams, UsersDatasetAggregateParams,
 };
 
 use self::fitness_v1_types::AggregateRequest;
 
 use super::Provider;
 
-const GOOGLE_FIT_DATA_SOURCE_ID: &'static str =
+const GOOGLE_FIT_STEPS_DATA_SOURCE_ID: &'static str =
     "derived:com.google.step_count.delta:com.google.android.gms:estimated_steps";
 const GOOGLE_FIT_STEPS_DATATYPE_NAME: &'static str = "com.google.step_count.delta";
 
+const GOOGLE_FIT_HEART_POINTS_DATA_SOURCE_ID: &'static str =
+    "derived:com.google.heart_minutes:com.google.android.gms:merge_heart_minutes";
+const GOOGLE_FIT_HEART_POINTS_DATATYPE_NAME: &'static str = "com.google.heart_minutes";
+
+const GOOGLE_FIT_CALORIES_DATA_SOURCE_ID: &'static str =
+    "derived:com.google.calories.expended:com.google.android.gms:merge_calories_expended";
+const GOOGLE_FIT_CALORIES_DATATYPE_NAME: &'static str = "com.google.calories.expended";
+
+const GOOGLE_FIT_DISTANCE_DATA_SOURCE_ID: &'static str =
+    "derived:com.google.distance.delta:com.google.android.gms:merge_distance_delta";
+const GOOGLE_FIT_DISTANCE_DATATYPE_NAME: &'static str = "com.google.distance.delta";
+
+const GOOGLE_FIT_ACTIVE_MINUTES_DATA_SOURCE_ID: &'static str =
+    "derived:com.google.active_minutes:com.google.android.gms:merge_active_minutes";
+const GOOGLE_FIT_ACTIVE_MINUTES_DATATYPE_NAME: &'static str = "com.google.active_minutes";
+
+const GOOGLE_FIT_ACTIVITY_SEGMENT_DATA_SOURCE_ID: &'static str =
+    "derived:com.google.activity.segment:com.google.android.gms:merge_activity_segments";
+const GOOGLE_FIT_ACTIVITY_SEGMENT_DATATYPE_NAME: &'static str = "com.google.activity.segment";
+
+/// Activity segment codes that represent not being active (e.g. still, in a vehicle) but
+/// are not sleep either. See https://developers.google.com/fit/rest/v1/reference/activity-types.
+const INACTIVE_ACTIVITY_CODES: [i32; 4] = [0, 2, 3, 4];
+/// Activity segment codes that represent some form of sleep.
+const SLEEP_ACTIVITY_CODES: [i32; 5] = [72, 109, 110, 111, 112];
+
+/// Name Hermes registers itself under when writing data sources to Google Fit.
+const HERMES_APPLICATION_NAME: &'static str = "hermes";
+
+/// Default path the OAuth token cache is persisted to when not overridden via
+/// `GoogleFitProvider::with_auth_options`.
+const DEFAULT_TOKEN_CACHE_PATH: &'static str = "tmp_client_token.json";
+
+/// Active vs. sedentary breakdown produced by `active_minutes_between`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActivityBreakdown {
+    pub active_minutes: i64,
+    pub sleep_minutes: i64,
+}
+
 /// GoogleFitProvider - Interfaces with the Google Fitness API
-/// to retrieve daily steps and heart points.
+/// to retrieve daily steps and heart points, and to write step data back.
 pub struct GoogleFitProvider {
     user_dataset_service: fitness_v1_types::UsersDatasetService,
+    user_data_source_service: fitness_v1_types::UsersDataSourceService,
 }
 
 impl GoogleFitProvider {
@@ -30,73 +70,167 @@ impl GoogleFitProvider {
     /// daily step count. Looks for the Google Client credentials using `GOOGLE_CLIENT_SECRET`
     /// if `client_secret_path` is not provided.
     /// Will launch an authentication flow process for the user to give the program the necessary permissions.
-    pub async fn new(client_secret_path: Option<String>) -> Result<GoogleFitProvider, ()> {
-        let client_secret_path = client_secret_path
-            .unwrap_or_else(|| std::env::var("GOOGLE_CLIENT_SECRET").unwrap().to_string());
-        GoogleFitProvider::validate(client_secret_path.clone());
+    pub async fn new(client_secret_path: Option<String>) -> Result<GoogleFitProvider, HermesError> {
+        GoogleFitProvider::with_auth_options(
+            client_secret_path,
+            DEFAULT_TOKEN_CACHE_PATH.to_string(),
+            AuthRedirectMethod::HttpRedirect,
+        )
+        .await
+    }
+
+    /// Like `new`, but lets the caller override where the OAuth token cache is persisted
+    /// and how the consent flow is completed. Use `AuthRedirectMethod::Interactive` when
+    /// running headless on a server with no local browser to redirect to.
+    pub async fn with_auth_options(
+        client_secret_path: Option<String>,
+        token_cache_path: String,
+        redirect_method: AuthRedirectMethod,
+    ) -> Result<GoogleFitProvider, HermesError> {
+        let client_secret_path = match client_secret_path {
+            Some(path) => path,
+            None => std::env::var("GOOGLE_CLIENT_SECRET").map_err(|_| {
+                HermesError::MissingCredentials("GOOGLE_CLIENT_SECRET is not set".to_string())
+            })?,
+        };
+        GoogleFitProvider::validate(&client_secret_path)?;
+
+        let auth_manager = AuthManager::new(client_secret_path, token_cache_path, redirect_method)
+            .await
+            .map_err(|e| HermesError::AuthFailure(e.to_string()))?;
+        let auth_manager = Arc::new(auth_manager);
+        let https_client = auth_manager.https_client();
 
-        let https_client = GoogleFitProvider::generate_https_client();
-        let auth = GoogleFitProvider::generate_auth(https_client.clone(), client_secret_path).await;
+        let user_dataset_service =
+            fitness_v1_types::UsersDatasetService::new(https_client.clone(), auth_manager.clone());
 
-        let mut user_dataset_service =
-            fitness_v1_types::UsersDatasetService::new(https_client, Arc::new(auth.clone()));
-        let scopes = vec![fitness_v1_types::FitnessScopes::FitnessActivityRead];
-        user_dataset_service.set_scopes(scopes);
+        let mut user_data_source_service =
+            fitness_v1_types::UsersDataSourceService::new(https_client, auth_manager);
+        user_data_source_service.set_scopes(vec![
+            fitness_v1_types::FitnessScopes::FitnessActivityWrite,
+        ]);
 
         Ok(GoogleFitProvider {
             user_dataset_service,
+            user_data_source_service,
         })
     }
 
-    async fn generate_auth(
-        https_client: common::TlsClient,
-        client_secret_path: String,
-    ) -> yup_oauth2::authenticator::Authenticator<HttpsConnector<HttpConnector>> {
-        
-        let secrets = common::yup_oauth2::read_application_secret(client_secret_path)
-            .await
-            .expect("client secret file is invalid");
+    fn validate(client_secret_path: &str) -> Result<(), HermesError> {
+        if !GoogleFitProvider::check_client_secret(client_secret_path) {
+            return Err(HermesError::MissingCredentials(format!(
+                "no client secret file at {}",
+                client_secret_path
+            )));
+        }
+        Ok(())
+    }
 
-        let auth =
-            InstalledFlowAuthenticator::builder(secrets, InstalledFlowReturnMethod::HTTPRedirect)
-                .persist_tokens_to_disk("tmp_client_token.json")
-                .hyper_client(https_client)
-                .build()
-                .await
-                .expect("Failed to authenticate");
-        auth
+    fn check_client_secret(client_secret_path: &str) -> bool {
+        std::path::Path::new(client_secret_path).exists()
     }
+}
 
-    fn generate_https_client() -> common::TlsClient {
-        let conn = hyper_rustls::HttpsConnector::with_native_roots();
-        let cl = hyper::Client::builder().build(conn);
-        cl
+#[async_trait]
+impl Provider for GoogleFitProvider {
+    async fn steps_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        bucket_duration: Option<chrono::Duration>,
+    ) -> Result<Vec<(DateTime<Utc>, i32)>, HermesError> {
+        let resp = self
+            .aggregate(
+                GOOGLE_FIT_STEPS_DATA_SOURCE_ID,
+                GOOGLE_FIT_STEPS_DATATYPE_NAME,
+                start,
+                end,
+                bucket_duration,
+                &[fitness_v1_types::FitnessScopes::FitnessActivityRead],
+            )
+            .await?;
+
+        self.get_int_buckets_from_resp(resp)
     }
 
-    fn validate(client_secret_path: String) {
-        if !GoogleFitProvider::check_client_secret(client_secret_path) {
-            panic!("Invalid client secret path");
-        };
+    async fn heart_points(&self) -> Result<f64, HermesError> {
+        let (start, end) = self.today_window();
+        let resp = self
+            .aggregate(
+                GOOGLE_FIT_HEART_POINTS_DATA_SOURCE_ID,
+                GOOGLE_FIT_HEART_POINTS_DATATYPE_NAME,
+                start,
+                end,
+                None,
+                &[fitness_v1_types::FitnessScopes::FitnessBodyRead],
+            )
+            .await?;
+
+        Ok(self
+            .get_float_buckets_from_resp(resp)?
+            .into_iter()
+            .map(|(_, v)| v)
+            .sum())
     }
 
-    fn check_client_secret(client_secret_path: String) -> bool {
-        std::path::Path::new(&client_secret_path).exists()
+    async fn calories_expended(&self) -> Result<f64, HermesError> {
+        let (start, end) = self.today_window();
+        let resp = self
+            .aggregate(
+                GOOGLE_FIT_CALORIES_DATA_SOURCE_ID,
+                GOOGLE_FIT_CALORIES_DATATYPE_NAME,
+                start,
+                end,
+                None,
+                &[fitness_v1_types::FitnessScopes::FitnessActivityRead],
+            )
+            .await?;
+
+        Ok(self
+            .get_float_buckets_from_resp(resp)?
+            .into_iter()
+            .map(|(_, v)| v)
+            .sum())
     }
-}
 
-#[async_trait]
-impl Provider for GoogleFitProvider {
-    async fn daily_steps(&self) -> anyhow::Result<i32> {
-        let request = self.generate_request();
-        let params = self.generate_params();
+    async fn distance_meters(&self) -> Result<f64, HermesError> {
+        let (start, end) = self.today_window();
         let resp = self
-            .user_dataset_service
-            .aggregate(&params, &request)
+            .aggregate(
+                GOOGLE_FIT_DISTANCE_DATA_SOURCE_ID,
+                GOOGLE_FIT_DISTANCE_DATATYPE_NAME,
+                start,
+                end,
+                None,
+                &[fitness_v1_types::FitnessScopes::FitnessLocationRead],
+            )
             .await?;
 
-        let steps = self.get_step_count_from_resp(resp);
+        Ok(self
+            .get_float_buckets_from_resp(resp)?
+            .into_iter()
+            .map(|(_, v)| v)
+            .sum())
+    }
 
-        steps
+    async fn active_minutes(&self) -> Result<i32, HermesError> {
+        let (start, end) = self.today_window();
+        let resp = self
+            .aggregate(
+                GOOGLE_FIT_ACTIVE_MINUTES_DATA_SOURCE_ID,
+                GOOGLE_FIT_ACTIVE_MINUTES_DATATYPE_NAME,
+                start,
+                end,
+                None,
+                &[fitness_v1_types::FitnessScopes::FitnessActivityRead],
+            )
+            .await?;
+
+        Ok(self
+            .get_int_buckets_from_resp(resp)?
+            .into_iter()
+            .map(|(_, v)| v)
+            .sum())
     }
 }
 
@@ -109,64 +243,379 @@ impl GoogleFitProvider {
         }
     }
 
-    /// Creates an AggregateRequest which requests all the steps between the current time
-    /// and the start of the current day (based on the current timezone).
-    fn generate_request(&self) -> AggregateRequest {
-        let (midnight, current, delta) = self.generate_timestamps_now_and_midnight();
-        let req = AggregateRequest {
+    /// `(start, end)` for "midnight (local time) through now", used by the single-metric
+    /// "today so far" methods.
+    fn today_window(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let end = Utc::now();
+        let start = end
+            .with_timezone(&chrono::Local)
+            .date()
+            .and_hms_milli(0, 0, 0, 0)
+            .with_timezone(&Utc);
+        (start, end)
+    }
+
+    /// Requests `data_type_name` from `data_source_id` between `start` and `end`, bucketed
+    /// by `bucket_duration` (or a single bucket spanning the whole window if `None`).
+    /// `scopes` should be the minimal set of scopes `data_type_name` actually requires, so
+    /// the token minted for this call carries no more access than it needs.
+    async fn aggregate(
+        &self,
+        data_source_id: &str,
+        data_type_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        bucket_duration: Option<chrono::Duration>,
+        scopes: &[fitness_v1_types::FitnessScopes],
+    ) -> Result<AggregateResponse, HermesError> {
+        let duration_millis = bucket_duration
+            .unwrap_or_else(|| end - start)
+            .num_milliseconds();
+        let request = AggregateRequest {
             aggregate_by: Some(vec![AggregateBy {
-                data_source_id: Some(String::from(GOOGLE_FIT_DATA_SOURCE_ID)),
-                data_type_name: Some(String::from(GOOGLE_FIT_STEPS_DATATYPE_NAME)),
+                data_source_id: Some(String::from(data_source_id)),
+                data_type_name: Some(String::from(data_type_name)),
             }]),
             bucket_by_time: Some(BucketByTime {
-                duration_millis: Some(delta),
+                duration_millis: Some(duration_millis.to_string()),
                 ..BucketByTime::default()
             }),
-            start_time_millis: Some(midnight),
-            end_time_millis: Some(current),
+            start_time_millis: Some(start.timestamp_millis().to_string()),
+            end_time_millis: Some(end.timestamp_millis().to_string()),
             ..AggregateRequest::default()
         };
 
-        req
+        Ok(self
+            .user_dataset_service
+            .aggregate(&self.generate_params(), &request, scopes)
+            .await?)
     }
 
-    /// Creates two UNIX timestamps: `(midnight, current, delta)`.
-    /// `midnight` is the UNIX timestamp from midnight (where "midnight" is relative to the local timezone).
-    /// `current` is the current UNIX timestamp.
-    /// `delta` is the number of milliseconds between the two timestamps.
-    fn generate_timestamps_now_and_midnight(&self) -> (String, String, String) {
-        let current_time = chrono::offset::Utc::now();
-        let midnight_time = chrono::offset::Local::today().and_hms_milli(0, 0, 0, 0);
+    /// Extracts per-bucket integer values from an `AggregateResponse`, keyed by each
+    /// bucket's start time. A dataset or point absent from the response (e.g. a quiet day
+    /// with no datapoints) contributes zero rather than erroring.
+    fn get_int_buckets_from_resp(
+        &self,
+        resp: AggregateResponse,
+    ) -> Result<Vec<(DateTime<Utc>, i32)>, HermesError> {
+        Self::bucket_values(resp, |val| val.int_val)
+    }
 
-        let current_time_utc = current_time.timestamp_millis();
-        let midnight_utc = midnight_time.timestamp_millis();
-        let delta = current_time_utc - midnight_utc;
-        (
-            midnight_utc.to_string(),
-            current_time_utc.to_string(),
-            delta.to_string(),
-        )
+    /// Extracts per-bucket floating point values from an `AggregateResponse`, keyed by each
+    /// bucket's start time. Assumes the values were requested as `fp_val`s.
+    fn get_float_buckets_from_resp(
+        &self,
+        resp: AggregateResponse,
+    ) -> Result<Vec<(DateTime<Utc>, f64)>, HermesError> {
+        Self::bucket_values(resp, |val| val.fp_val)
     }
 
-    /// Extracts the steps from AggregateResponse. Assumes the appropriate AggregateRequest was sent.
-    fn get_step_count_from_resp(&self, resp: AggregateResponse) -> anyhow::Result<i32> {
-        let steps = resp
-            .bucket
-            .unwrap()
+    /// A bucket's own window, not a data point's, is the source of truth for its start
+    /// time: an empty bucket (no points at all, a legitimately quiet hour) still has a
+    /// real window. A bucket missing or mangling `startTimeMillis` is a malformed
+    /// response, not a quiet one, so it errors rather than silently reporting epoch.
+    fn bucket_values<T: Default + std::iter::Sum>(
+        resp: AggregateResponse,
+        extract: impl Fn(&fitness_v1_types::Value) -> Option<T>,
+    ) -> Result<Vec<(DateTime<Utc>, T)>, HermesError> {
+        resp.bucket
+            .unwrap_or_default()
             .iter()
-            .flat_map(|aggregate_bucket| {
-                aggregate_bucket
+            .map(|aggregate_bucket| {
+                let value = aggregate_bucket
                     .dataset
                     .as_ref()
-                    .expect("AggregateBucket has no datapoints")
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|dataset| dataset.point.as_ref().into_iter().flatten())
+                    .flat_map(|point| point.value.as_ref().into_iter().flatten())
+                    .filter_map(&extract)
+                    .sum::<T>();
+
+                let start_millis: i64 = aggregate_bucket
+                    .start_time_millis
+                    .as_ref()
+                    .ok_or_else(|| {
+                        HermesError::MalformedResponse(
+                            "aggregate bucket is missing startTimeMillis".to_string(),
+                        )
+                    })?
+                    .parse()
+                    .map_err(|_| {
+                        HermesError::MalformedResponse(format!(
+                            "aggregate bucket has a non-numeric startTimeMillis: {:?}",
+                            aggregate_bucket.start_time_millis
+                        ))
+                    })?;
+
+                let bucket_start = chrono::Utc.timestamp_millis(start_millis);
+                Ok((bucket_start, value))
             })
-            .flat_map(|dataset| dataset.point.as_ref().expect("Invalid dataset"))
-            .flat_map(|point| point.value.as_ref().expect("Empty datapoint").iter())
-            .map(|val| val.int_val.expect("Invalid data value"))
-            .collect::<Vec<i32>>()
+            .collect()
+    }
+
+    /// Returns the Hermes-owned `DataSource` named `name` for `data_type` if one has
+    /// already been registered, without creating anything.
+    async fn find_data_source(
+        &self,
+        name: &str,
+        data_type: &str,
+    ) -> Result<Option<DataSource>, HermesError> {
+        let existing = self
+            .user_data_source_service
+            .list(&self.data_source_params())
+            .await?;
+
+        Ok(existing
+            .data_source
+            .unwrap_or_default()
             .into_iter()
-            .sum::<i32>();
+            .find(|data_source| {
+                let application_matches = data_source
+                    .application
+                    .as_ref()
+                    .and_then(|app| app.name.as_deref())
+                    == Some(name);
+                let data_type_matches = data_source
+                    .data_type
+                    .as_ref()
+                    .and_then(|dt| dt.name.as_deref())
+                    == Some(data_type);
+                application_matches && data_type_matches
+            }))
+    }
+
+    /// Registers a `DataSource` named `name` for writing `data_type` values into Google
+    /// Fit, reusing one already registered under that name rather than re-creating it
+    /// (the Fitness API rejects a duplicate `DataSource` for the same stream). Required
+    /// before `write_steps` can PATCH a dataset onto it.
+    pub async fn create_data_source(
+        &self,
+        name: &str,
+        data_type: &str,
+    ) -> Result<DataSource, HermesError> {
+        if let Some(existing) = self.find_data_source(name, data_type).await? {
+            return Ok(existing);
+        }
+
+        let data_source = DataSource {
+            type_: Some("raw".to_string()),
+            application: Some(Application {
+                name: Some(name.to_string()),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+            data_type: Some(DataType {
+                name: Some(data_type.to_string()),
+                field: Some(vec![DataTypeField {
+                    name: Some("steps".to_string()),
+                    format: Some("integer".to_string()),
+                }]),
+            }),
+            data_stream_id: None,
+        };
+
+        Ok(self
+            .user_data_source_service
+            .create(&self.data_source_params(), &data_source)
+            .await?)
+    }
+
+    /// Writes `count` steps covering `[start, end)` into Google Fit, via
+    /// `create_data_source`'s Hermes-owned data source for step writes (reused across
+    /// calls rather than re-registered each time).
+    pub async fn write_steps(
+        &self,
+        count: i32,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(), HermesError> {
+        let data_source = self
+            .create_data_source(HERMES_APPLICATION_NAME, GOOGLE_FIT_STEPS_DATATYPE_NAME)
+            .await?;
+        let data_source_id = data_source.data_stream_id.ok_or_else(|| {
+            HermesError::MalformedResponse("created data source has no dataStreamId".to_string())
+        })?;
+
+        let start_nanos = start.timestamp_millis() * 1_000_000;
+        let end_nanos = end.timestamp_millis() * 1_000_000;
+        let dataset_id = format!("{}-{}", start_nanos, end_nanos);
+
+        let dataset = DatasetPatchRequest {
+            data_source_id: Some(data_source_id.clone()),
+            min_start_time_ns: Some(start_nanos.to_string()),
+            max_end_time_ns: Some(end_nanos.to_string()),
+            point: Some(vec![fitness_v1_types::DataPoint {
+                start_time_nanos: Some(start_nanos.to_string()),
+                end_time_nanos: Some(end_nanos.to_string()),
+                value: Some(vec![fitness_v1_types::Value {
+                    int_val: Some(count),
+                    fp_val: None,
+                }]),
+            }]),
+        };
+
+        self.user_data_source_service
+            .datasets_patch(&self.data_source_params(), &data_source_id, &dataset_id, &dataset)
+            .await?;
+
+        Ok(())
+    }
+
+    fn data_source_params(&self) -> UsersDataSourceParams {
+        UsersDataSourceParams {
+            user_id: "me".to_string(),
+        }
+    }
+
+    /// Aggregates `com.google.activity.segment` between `start` and `end` and classifies
+    /// each segment's duration as active or sleep, per `INACTIVE_ACTIVITY_CODES` and
+    /// `SLEEP_ACTIVITY_CODES`.
+    pub async fn active_minutes_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<ActivityBreakdown, HermesError> {
+        let resp = self
+            .aggregate(
+                GOOGLE_FIT_ACTIVITY_SEGMENT_DATA_SOURCE_ID,
+                GOOGLE_FIT_ACTIVITY_SEGMENT_DATATYPE_NAME,
+                start,
+                end,
+                None,
+                &[fitness_v1_types::FitnessScopes::FitnessActivityRead],
+            )
+            .await?;
+
+        Ok(Self::classify_activity_segments(resp))
+    }
+
+    fn classify_activity_segments(resp: AggregateResponse) -> ActivityBreakdown {
+        // Accumulated in nanoseconds rather than minutes, then converted once at the end:
+        // a segment shorter than 60 seconds is still real elapsed time, and summing
+        // per-segment minutes first would truncate every such segment down to zero.
+        let mut active_nanos: i64 = 0;
+        let mut sleep_nanos: i64 = 0;
+
+        for point in resp
+            .bucket
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|bucket| bucket.dataset.unwrap_or_default())
+            .flat_map(|dataset| dataset.point.unwrap_or_default())
+        {
+            let activity_code = point
+                .value
+                .as_ref()
+                .and_then(|values| values.first())
+                .and_then(|value| value.int_val);
+            let start_nanos = point
+                .start_time_nanos
+                .as_ref()
+                .and_then(|nanos| nanos.parse::<i64>().ok());
+            let end_nanos = point
+                .end_time_nanos
+                .as_ref()
+                .and_then(|nanos| nanos.parse::<i64>().ok());
+
+            let (code, start_nanos, end_nanos) = match (activity_code, start_nanos, end_nanos) {
+                (Some(code), Some(start_nanos), Some(end_nanos)) => (code, start_nanos, end_nanos),
+                _ => continue,
+            };
+
+            let duration_nanos = end_nanos - start_nanos;
+            if SLEEP_ACTIVITY_CODES.contains(&code) {
+                sleep_nanos += duration_nanos;
+            } else if !INACTIVE_ACTIVITY_CODES.contains(&code) {
+                active_nanos += duration_nanos;
+            }
+        }
+
+        ActivityBreakdown {
+            active_minutes: active_nanos / 1_000_000_000 / 60,
+            sleep_minutes: sleep_nanos / 1_000_000_000 / 60,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::google::fitness_v1_types::{AggregateBucket, DataPoint, Dataset, Value};
+
+    fn segment(code: i32, start_nanos: i64, duration_nanos: i64) -> DataPoint {
+        DataPoint {
+            start_time_nanos: Some(start_nanos.to_string()),
+            end_time_nanos: Some((start_nanos + duration_nanos).to_string()),
+            value: Some(vec![Value {
+                int_val: Some(code),
+                fp_val: None,
+            }]),
+        }
+    }
+
+    fn response_with(points: Vec<DataPoint>) -> AggregateResponse {
+        AggregateResponse {
+            bucket: Some(vec![AggregateBucket {
+                start_time_millis: None,
+                end_time_millis: None,
+                dataset: Some(vec![Dataset { point: Some(points) }]),
+            }]),
+        }
+    }
+
+    #[test]
+    fn sums_sub_minute_segments_into_whole_minutes() {
+        // 90 one-second "walking" (code 7) segments: no single segment reaches a full
+        // minute on its own, but their combined 90s should still round down to 1 active
+        // minute rather than truncating each segment to 0 before summing.
+        let points: Vec<DataPoint> = (0..90)
+            .map(|i| segment(7, i * 1_000_000_000, 1_000_000_000))
+            .collect();
+
+        let breakdown = GoogleFitProvider::classify_activity_segments(response_with(points));
+
+        assert_eq!(breakdown.active_minutes, 1);
+        assert_eq!(breakdown.sleep_minutes, 0);
+    }
+
+    #[test]
+    fn classifies_inactive_sleep_and_active_codes() {
+        let points = vec![
+            // Inactive codes contribute to neither bucket.
+            segment(0, 0, 600_000_000_000),
+            segment(2, 0, 600_000_000_000),
+            segment(3, 0, 600_000_000_000),
+            segment(4, 0, 600_000_000_000),
+            // Sleep codes accumulate into sleep_minutes, 5 minutes each.
+            segment(72, 0, 5 * 60_000_000_000),
+            segment(109, 0, 5 * 60_000_000_000),
+            segment(110, 0, 5 * 60_000_000_000),
+            segment(111, 0, 5 * 60_000_000_000),
+            segment(112, 0, 5 * 60_000_000_000),
+            // An active code (7 == walking) accumulates into active_minutes.
+            segment(7, 0, 10 * 60_000_000_000),
+        ];
+
+        let breakdown = GoogleFitProvider::classify_activity_segments(response_with(points));
+
+        assert_eq!(breakdown.sleep_minutes, 25);
+        assert_eq!(breakdown.active_minutes, 10);
+    }
+
+    #[test]
+    fn ignores_points_missing_code_or_timestamps() {
+        let malformed = DataPoint {
+            start_time_nanos: None,
+            end_time_nanos: Some("1".to_string()),
+            value: Some(vec![Value {
+                int_val: Some(7),
+                fp_val: None,
+            }]),
+        };
+
+        let breakdown =
+            GoogleFitProvider::classify_activity_segments(response_with(vec![malformed]));
 
-        anyhow::Result::Ok(steps)
+        assert_eq!(breakdown, ActivityBreakdown::default());
     }
 }
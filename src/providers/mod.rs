@@ -1,9 +1,45 @@
+pub mod auth;
 pub mod google;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::error::HermesError;
 
 #[async_trait]
 pub trait Provider {
     /// Returns the total amount of steps that the user has taken between now and midnight.
-    /// "midnight" is relative to the current time.
-    async fn daily_steps(&self) -> anyhow::Result<i32>;
+    /// "midnight" is relative to the current time. A thin wrapper around `steps_between`
+    /// for the common "today so far" case.
+    async fn daily_steps(&self) -> Result<i32, HermesError> {
+        let end = Utc::now();
+        let start = end
+            .with_timezone(&chrono::Local)
+            .date()
+            .and_hms_milli(0, 0, 0, 0)
+            .with_timezone(&Utc);
+        let steps = self.steps_between(start, end, None).await?;
+        Ok(steps.into_iter().map(|(_, count)| count).sum())
+    }
+
+    /// Returns the steps taken between `start` and `end`, broken down into buckets of
+    /// `bucket_duration`. When `bucket_duration` is `None` the whole window is treated as
+    /// a single bucket. Each entry in the result is `(bucket_start, steps_in_bucket)`.
+    async fn steps_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        bucket_duration: Option<chrono::Duration>,
+    ) -> Result<Vec<(DateTime<Utc>, i32)>, HermesError>;
+
+    /// Returns the total heart points accumulated today (midnight to now).
+    async fn heart_points(&self) -> Result<f64, HermesError>;
+
+    /// Returns the total calories, in kcal, expended today (midnight to now).
+    async fn calories_expended(&self) -> Result<f64, HermesError>;
+
+    /// Returns the total distance traveled today, in meters (midnight to now).
+    async fn distance_meters(&self) -> Result<f64, HermesError>;
+
+    /// Returns the total active minutes recorded today (midnight to now).
+    async fn active_minutes(&self) -> Result<i32, HermesError>;
 }